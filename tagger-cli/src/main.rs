@@ -3,6 +3,9 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use tagger::App;
 
+mod query;
+use query::parse_tag_query;
+
 #[derive(Debug, Parser)]
 #[command(name = "tagger")]
 struct Cli {
@@ -21,9 +24,13 @@ enum Commands {
         tag: String,
     },
     Update {},
-    Scan {},
+    Scan {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
     Search {
-        tag: String,
+        /// e.g. "cat AND (dog OR bird) NOT archived"
+        query: String,
     },
 }
 
@@ -43,14 +50,14 @@ fn main() -> Result<()> {
         }
         Commands::Tag { filename, tag } => {
             let filename = path_to_string(filename)?;
-            let file_id = match app.get_file(&filename) {
+            let file_id = match app.get_file(&filename)? {
                 Some(id) => id,
                 None => {
                     println!("File wasn't being tracked, tracking it now...");
                     app.create_file(&filename)?
                 }
             };
-            let tag_id = match app.get_tag(&tag) {
+            let tag_id = match app.get_tag(&tag)? {
                 Some(id) => id,
                 None => {
                     println!("Tag didn't exist, making it now...");
@@ -58,23 +65,31 @@ fn main() -> Result<()> {
                 }
             };
 
-            app.tag_file(tag_id, file_id);
+            app.tag_file(tag_id, file_id)?;
         }
-        Commands::Scan {} => {
-            println!("This would walk the directory and find files to add and mark missing files as orphaned")
+        Commands::Scan { path } => {
+            let report = app.scan(&path, tagger::ScanOptions::default())?;
+            println!(
+                "Scan complete: {} added, {} seen, {} orphaned",
+                report.added, report.seen, report.orphaned
+            );
         }
         Commands::Update {} => {
             println!("This command would take a file, and update it's tags.")
         }
 
-        Commands::Search { tag } => match app.get_tag(&tag) {
-            None => println!("This tag doesn't exist."),
-            Some(tag_id) => {
-                for file in app.get_files_for_tag(tag_id)? {
-                    println!("{}", file.file_name);
-                }
+        Commands::Search { query } => {
+            let tag_query = parse_tag_query(&query)?;
+            for file in app.search(&tag_query)? {
+                println!(
+                    "{}\t{} bytes\tmodified {}\t{}",
+                    file.file_name,
+                    file.size_bytes,
+                    file.modified_at,
+                    file.mime_type.as_deref().unwrap_or("unknown")
+                );
             }
-        },
+        }
     }
 
     Ok(())
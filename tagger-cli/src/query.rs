@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use tagger::TagQuery;
+
+/// Parses a query like `cat AND (dog OR bird) NOT archived` into a `TagQuery`.
+/// `AND` between terms is optional: juxtaposed terms are implicitly ANDed together.
+pub fn parse_tag_query(input: &str) -> Result<TagQuery> {
+    let tokens = tokenize(input);
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(anyhow!("Unexpected input in query after position {}", parser.pos));
+    }
+    Ok(expr)
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<TagQuery> {
+        let mut terms = vec![self.parse_and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+            self.next();
+            terms.push(self.parse_and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            TagQuery::Any(terms)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<TagQuery> {
+        let mut terms = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(t) if t.eq_ignore_ascii_case("AND") => {
+                    self.next();
+                    terms.push(self.parse_unary()?);
+                }
+                Some(t) if t.eq_ignore_ascii_case("OR") || t == ")" => break,
+                None => break,
+                // Juxtaposed terms (e.g. "cat NOT archived") are implicitly ANDed.
+                Some(_) => terms.push(self.parse_unary()?),
+            }
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            TagQuery::All(terms)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<TagQuery> {
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case("NOT")) {
+            self.next();
+            return Ok(TagQuery::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<TagQuery> {
+        match self.next() {
+            Some("(") => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(")") => Ok(expr),
+                    _ => Err(anyhow!("Expected closing parenthesis")),
+                }
+            }
+            Some(tag) => Ok(TagQuery::Tag(tag.to_string())),
+            None => Err(anyhow!("Expected a tag, '(' or NOT")),
+        }
+    }
+}
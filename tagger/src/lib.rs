@@ -1,10 +1,15 @@
 use include_dir::{include_dir, Dir};
 use lazy_static::lazy_static;
-use rusqlite::Connection;
+use rusqlite::{params, Connection, Transaction};
 use rusqlite_migration::Migrations;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File as FsFile;
+use std::io::{self, BufReader, Read};
 use std::path::Path;
 use thiserror::Error;
 use time::OffsetDateTime;
+use walkdir::WalkDir;
 
 #[repr(C)]
 pub struct App {
@@ -31,23 +36,100 @@ impl App {
         Ok(Self { connection })
     }
 
-    // TODO: Handle duplicate files?
+    // Identical content under a new path re-uses the existing row (and its tags) instead of
+    // creating a duplicate, so a renamed/moved file isn't treated as orphaned-plus-new — unless
+    // the old path is still present on disk, in which case this is a coexisting duplicate (e.g.
+    // two empty files) and must get its own row rather than steal the other's.
     pub fn create_file(&mut self, filename: &str) -> Result<FileID, TaggerError> {
-        self.connection
-            .execute("INSERT INTO files (filename) VALUES (?1)", [filename])?;
+        let hash = hash_file(filename)?;
+        let candidates = find_rows_by_hash(&self.connection, &hash)?;
+
+        if let Some(id) = pick_reusable_row(&candidates, filename) {
+            let attributes = stat_file(filename)?;
+            self.connection.execute(
+                "UPDATE files SET filename = ?1, size_bytes = ?2, modified_at = ?3, mime_type = ?4
+                 WHERE id = ?5",
+                params![
+                    filename,
+                    attributes.size_bytes,
+                    attributes.modified_at,
+                    attributes.mime_type,
+                    id
+                ],
+            )?;
+            return Ok(FileID(id));
+        }
+
+        let attributes = stat_file(filename)?;
+        self.connection.execute(
+            "INSERT INTO files (filename, content_hash, size_bytes, modified_at, mime_type)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                filename,
+                hash,
+                attributes.size_bytes,
+                attributes.modified_at,
+                attributes.mime_type
+            ],
+        )?;
         let id = self.connection.last_insert_rowid();
         Ok(FileID(id))
     }
 
-    pub fn get_file(&mut self, filename: &str) -> Option<FileID> {
+    // Opens a single transaction and prepares its statements once, so a scan of thousands of
+    // files doesn't pay a separate commit/fsync per row.
+    pub fn create_files<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        names: I,
+    ) -> Result<Vec<FileID>, TaggerError> {
+        let tx = self.connection.transaction()?;
+        let ids = create_files_in_tx(&tx, names)?;
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    pub fn tag_files(&mut self, pairs: &[(TagID, FileID)]) -> Result<(), TaggerError> {
+        let tx = self.connection.transaction()?;
+        {
+            let mut insert_tag =
+                tx.prepare("INSERT INTO file_tags (file_id, tag_id) VALUES (?1, ?2)")?;
+            for (tag, file) in pairs {
+                insert_tag.execute([file.0, tag.0])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_files_by_mime(&mut self, mime_type: &str) -> Result<Vec<File>, TaggerError> {
+        let query = "
+            SELECT id,
+                filename,
+                last_seen_at,
+                orphaned_at,
+                updated_at,
+                created_at,
+                size_bytes,
+                modified_at,
+                mime_type
+            FROM files
+            WHERE mime_type = ?1";
+        let mut statement = self.connection.prepare(query)?;
+        let files = statement
+            .query_map([mime_type], row_extract::<File>)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    pub fn get_file(&mut self, filename: &str) -> Result<Option<FileID>, TaggerError> {
         match self.connection.query_row(
             "SELECT id FROM files WHERE filename = ?1",
             [filename],
-            |r| r.get(0),
+            row_extract::<FileID>,
         ) {
-            Ok(id) => Some(FileID(id)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => None,
-            Err(e) => panic!("SQL Error, {}", e),
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -65,27 +147,24 @@ impl App {
         }
     }
 
-    pub fn get_tag(&mut self, tag_name: &str) -> Option<TagID> {
-        match self
-            .connection
-            .query_row("SELECT id FROM tags WHERE name = ?1", [&tag_name], |r| {
-                r.get(0)
-            }) {
-            Ok(id) => Some(TagID(id)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => None,
-            Err(e) => panic!("SQL Error, {}", e),
+    pub fn get_tag(&mut self, tag_name: &str) -> Result<Option<TagID>, TaggerError> {
+        match self.connection.query_row(
+            "SELECT id FROM tags WHERE name = ?1",
+            [&tag_name],
+            row_extract::<TagID>,
+        ) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
 
-    pub fn tag_file(&mut self, tag: TagID, file: FileID) {
-        let res = self.connection.execute(
+    pub fn tag_file(&mut self, tag: TagID, file: FileID) -> Result<(), TaggerError> {
+        self.connection.execute(
             "INSERT INTO file_tags (file_id, tag_id) VALUES (?1, ?2)",
             [file.0, tag.0],
-        );
-        if let Err(e) = res {
-            println!("SQL ERROR {}", e);
-            panic!("SQL Error, {}", e);
-        }
+        )?;
+        Ok(())
     }
 
     pub fn get_files_for_tag(&mut self, tag: TagID) -> Result<Vec<File>, TaggerError> {
@@ -95,28 +174,229 @@ impl App {
                 files.last_seen_at,
                 files.orphaned_at,
                 files.updated_at,
-                files.created_at 
-            FROM file_tags 
+                files.created_at,
+                files.size_bytes,
+                files.modified_at,
+                files.mime_type
+            FROM file_tags
             INNER JOIN files ON files.id = file_tags.file_id
             WHERE file_tags.tag_id = ?1";
         let mut statement = self.connection.prepare(query)?;
-        let files: Vec<_> = statement
-            .query_map([tag.0], |row| {
-                Ok(File {
-                    id: FileID(row.get(0).expect("SQL Error")),
-                    file_name: row.get(1).expect("SQL Error"),
-                    last_seen_at: row.get(2).expect("SQL Error"),
-                    orphaned_at: row.get(3).expect("SQL Error"),
-                    updated_at: row.get(4).expect("SQL Error"),
-                    created_at: row.get(5).expect("SQL Error"),
-                })
-            })?
-            .into_iter()
-            .map(|result| result.ok())
-            .flatten()
-            .collect();
+        let files = statement
+            .query_map([tag.0], row_extract::<File>)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    // Compiles `expr` into a single SQL query over file_tags, combining per-tag subqueries with
+    // INTERSECT/UNION/EXCEPT, so callers aren't limited to a single tag lookup.
+    pub fn search(&mut self, expr: &TagQuery) -> Result<Vec<File>, TaggerError> {
+        let mut params = Vec::new();
+        let matching_ids = compile_tag_query(expr, &mut params);
+        let query = format!(
+            "SELECT id,
+                filename,
+                last_seen_at,
+                orphaned_at,
+                updated_at,
+                created_at,
+                size_bytes,
+                modified_at,
+                mime_type
+            FROM files
+            WHERE id IN ({matching_ids})"
+        );
+        let mut statement = self.connection.prepare(&query)?;
+        let files = statement
+            .query_map(rusqlite::params_from_iter(params.iter()), row_extract::<File>)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
         Ok(files)
     }
+
+    // Walks `root`, upserting every file it finds and marking anything previously tracked under
+    // `root` that wasn't seen this pass as orphaned (clearing orphaned_at if it reappears).
+    pub fn scan<P: AsRef<Path>>(
+        &mut self,
+        root: P,
+        options: ScanOptions,
+    ) -> Result<ScanReport, TaggerError> {
+        let root = root.as_ref();
+        let now = OffsetDateTime::now_utc();
+        let mut report = ScanReport::default();
+
+        // The LIKE clause only narrows candidates down to files whose name starts with `root` as
+        // a string (escaped so `%`/`_` in the root path can't act as wildcards) — it's cheap
+        // insurance against scanning every tracked row in a database with many scan roots. The
+        // exact test is `Path::starts_with` below, which compares whole path components so it
+        // can't mistake a sibling like `<root>-backup/...` for a descendant of `root`.
+        let mut previously_tracked: HashSet<i64> = {
+            let mut statement = self.connection.prepare(
+                "SELECT id, filename FROM files
+                 WHERE orphaned_at IS NULL AND filename LIKE ?1 ESCAPE '\\'",
+            )?;
+            statement
+                .query_map([like_prefix_pattern(&root.to_string_lossy())], |r| {
+                    Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))
+                })?
+                .filter_map(|row| row.ok())
+                .filter(|(_, filename)| Path::new(filename).starts_with(root))
+                .map(|(id, _)| id)
+                .collect()
+        };
+
+        let known_before_scan: HashSet<i64> = {
+            let mut statement = self.connection.prepare("SELECT id FROM files")?;
+            statement
+                .query_map([], |r| r.get(0))?
+                .collect::<rusqlite::Result<_>>()?
+        };
+
+        let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+            if !entry.file_type().is_dir() {
+                return true;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                if options.ignored_dirs.iter().any(|ignored| ignored == name) {
+                    return false;
+                }
+            }
+            if options.respect_cachedir_tags && is_cachedir_tagged(entry.path()) {
+                return false;
+            }
+            true
+        });
+
+        let paths: Vec<String> = walker
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.path().to_str().map(str::to_string))
+            .collect();
+
+        let tx = self.connection.transaction()?;
+        let ids = create_files_in_tx(&tx, paths.iter().map(String::as_str))?;
+        // A single path never yields the same row twice, but a repeated path in the walk (or two
+        // rows legitimately resolving to one, pre-dedup fix) would otherwise double-count one row
+        // as two in the report.
+        let unique_ids: HashSet<i64> = ids.into_iter().map(|id| id.0).collect();
+        for id in unique_ids {
+            if !known_before_scan.contains(&id) {
+                report.added += 1;
+            }
+            tx.execute(
+                "UPDATE files SET last_seen_at = ?1, orphaned_at = NULL WHERE id = ?2",
+                params![now, id],
+            )?;
+            report.seen += 1;
+            previously_tracked.remove(&id);
+        }
+
+        for id in previously_tracked {
+            tx.execute(
+                "UPDATE files SET orphaned_at = ?1 WHERE id = ?2",
+                params![now, id],
+            )?;
+            report.orphaned += 1;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub added: usize,
+    pub seen: usize,
+    pub orphaned: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub respect_cachedir_tags: bool,
+    pub ignored_dirs: Vec<String>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            respect_cachedir_tags: true,
+            ignored_dirs: vec![".git".to_string(), "node_modules".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TagQuery {
+    All(Vec<TagQuery>),
+    Any(Vec<TagQuery>),
+    Not(Box<TagQuery>),
+    Tag(String),
+}
+
+// Recursively lowers a `TagQuery` into a SQL expression selecting matching `files.id`s, pushing
+// each tag name it references onto `params` in the same order as the `?` placeholders it emits.
+//
+// SQLite evaluates chained compound operators (INTERSECT/UNION/EXCEPT) strictly left-to-right at
+// equal precedence, so a flat join like `a INTERSECT b UNION c` doesn't honour `TagQuery`'s tree
+// structure. Each child is instead wrapped as `SELECT id FROM (<child>)` before joining, so
+// nesting in the `TagQuery` tree maps onto nesting in the SQL rather than operator precedence.
+fn compile_tag_query(expr: &TagQuery, params: &mut Vec<String>) -> String {
+    match expr {
+        TagQuery::Tag(name) => {
+            params.push(name.clone());
+            "SELECT file_tags.file_id AS id FROM file_tags \
+             INNER JOIN tags ON tags.id = file_tags.tag_id \
+             WHERE tags.name = ?"
+                .to_string()
+        }
+        // An empty `All` is vacuously true (no constraint excludes anything); an empty `Any` is
+        // vacuously false. Falling through to `.join(...)` on an empty `Vec` would otherwise
+        // lower to an empty string, producing invalid SQL (`WHERE id IN ()`) in `App::search`.
+        TagQuery::All(children) if children.is_empty() => "SELECT id FROM files".to_string(),
+        TagQuery::Any(children) if children.is_empty() => {
+            "SELECT id FROM files WHERE 0".to_string()
+        }
+        TagQuery::All(children) => children
+            .iter()
+            .map(|child| format!("SELECT id FROM ({})", compile_tag_query(child, params)))
+            .collect::<Vec<_>>()
+            .join(" INTERSECT "),
+        TagQuery::Any(children) => children
+            .iter()
+            .map(|child| format!("SELECT id FROM ({})", compile_tag_query(child, params)))
+            .collect::<Vec<_>>()
+            .join(" UNION "),
+        TagQuery::Not(inner) => format!(
+            "SELECT id FROM files EXCEPT SELECT id FROM ({})",
+            compile_tag_query(inner, params)
+        ),
+    }
+}
+
+// Builds a `LIKE ... ESCAPE '\'` pattern matching strings starting with `prefix`, escaping `%`,
+// `_` and the escape character itself so they're matched literally rather than as wildcards.
+fn like_prefix_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+// Per the CACHEDIR.TAG convention (https://bford.info/cachedir/): a directory is a cache root if
+// it holds a CACHEDIR.TAG file starting with this exact signature.
+const CACHEDIR_TAG_SIGNATURE: &[u8; 43] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+fn is_cachedir_tagged(dir: &Path) -> bool {
+    let Ok(mut file) = FsFile::open(dir.join("CACHEDIR.TAG")) else {
+        return false;
+    };
+    let mut signature = [0u8; 43];
+    file.read_exact(&mut signature).is_ok() && &signature == CACHEDIR_TAG_SIGNATURE
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -132,6 +412,47 @@ pub struct File {
     pub orphaned_at: Option<OffsetDateTime>,
     pub updated_at: OffsetDateTime,
     pub created_at: OffsetDateTime,
+    pub size_bytes: i64,
+    pub modified_at: OffsetDateTime,
+    pub mime_type: Option<String>,
+}
+
+// Maps a query's leading columns onto a type, so adding a query doesn't mean adding another
+// hand-rolled `row.get(n).expect(...)` block that panics on bad data.
+pub trait FromRow: Sized {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self>;
+}
+
+fn row_extract<T: FromRow>(row: &rusqlite::Row) -> rusqlite::Result<T> {
+    T::from_row(row)
+}
+
+impl FromRow for FileID {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(FileID(row.get(0)?))
+    }
+}
+
+impl FromRow for TagID {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(TagID(row.get(0)?))
+    }
+}
+
+impl FromRow for File {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(File {
+            id: FileID(row.get(0)?),
+            file_name: row.get(1)?,
+            last_seen_at: row.get(2)?,
+            orphaned_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            created_at: row.get(5)?,
+            size_bytes: row.get(6)?,
+            modified_at: row.get(7)?,
+            mime_type: row.get(8)?,
+        })
+    }
 }
 
 #[derive(Error, Debug)]
@@ -140,11 +461,125 @@ pub enum TaggerError {
     DirectoryError(),
     #[error("SQLite Issue")]
     DatabaseError(#[from] rusqlite::Error),
+    #[error("IO Error")]
+    IoError(#[from] io::Error),
+}
+
+// Shared core of `create_files`, taking an already-open transaction so `scan` can fold the
+// upsert and the last_seen_at/orphaned_at bookkeeping into a single transaction.
+fn create_files_in_tx<'a, I: IntoIterator<Item = &'a str>>(
+    tx: &Transaction,
+    names: I,
+) -> Result<Vec<FileID>, TaggerError> {
+    let mut ids = Vec::new();
+    let mut find_by_hash =
+        tx.prepare("SELECT id, filename FROM files WHERE content_hash = ?1")?;
+    let mut update_filename = tx.prepare(
+        "UPDATE files SET filename = ?1, size_bytes = ?2, modified_at = ?3, mime_type = ?4
+         WHERE id = ?5",
+    )?;
+    let mut insert_file = tx.prepare(
+        "INSERT INTO files (filename, content_hash, size_bytes, modified_at, mime_type)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+
+    for name in names {
+        let hash = hash_file(name)?;
+        let candidates: Vec<(i64, String)> = find_by_hash
+            .query_map([&hash], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let attributes = stat_file(name)?;
+
+        let id = match pick_reusable_row(&candidates, name) {
+            // The file merely moved: re-use the existing row (and its tags) instead of
+            // treating it as orphaned-plus-new.
+            Some(id) => {
+                update_filename.execute(params![
+                    name,
+                    attributes.size_bytes,
+                    attributes.modified_at,
+                    attributes.mime_type,
+                    id
+                ])?;
+                id
+            }
+            None => {
+                insert_file.execute(params![
+                    name,
+                    hash,
+                    attributes.size_bytes,
+                    attributes.modified_at,
+                    attributes.mime_type
+                ])?;
+                tx.last_insert_rowid()
+            }
+        };
+        ids.push(FileID(id));
+    }
+    Ok(ids)
+}
+
+// All rows currently sharing `hash`, used to choose a reuse candidate for `create_file`
+// (`create_files_in_tx` runs the same query through its own prepared statement).
+fn find_rows_by_hash(conn: &Connection, hash: &[u8]) -> Result<Vec<(i64, String)>, TaggerError> {
+    let mut statement = conn.prepare("SELECT id, filename FROM files WHERE content_hash = ?1")?;
+    let rows = statement
+        .query_map([hash], |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+// Picks which of several same-hash rows (if any) `name` should reuse: the row already at this
+// exact path, or else one whose stored path is gone (an actual move). If every same-hash row's
+// path is still present on disk, `name` is a new, coexisting duplicate and must get its own row
+// rather than steal one of theirs.
+fn pick_reusable_row(candidates: &[(i64, String)], name: &str) -> Option<i64> {
+    candidates
+        .iter()
+        .find(|(_, stored_name)| stored_name == name)
+        .or_else(|| candidates.iter().find(|(_, stored_name)| !Path::new(stored_name).exists()))
+        .map(|(id, _)| *id)
+}
+
+struct FileAttributes {
+    size_bytes: i64,
+    modified_at: OffsetDateTime,
+    mime_type: Option<String>,
+}
+
+fn stat_file(path: &str) -> Result<FileAttributes, TaggerError> {
+    let metadata = std::fs::metadata(path)?;
+    Ok(FileAttributes {
+        size_bytes: metadata.len() as i64,
+        modified_at: metadata.modified()?.into(),
+        mime_type: mime_guess::from_path(path)
+            .first()
+            .map(|mime| mime.to_string()),
+    })
+}
+
+// Streams the file rather than reading it whole, so hashing doesn't blow out memory on large
+// files during a scan.
+fn hash_file(path: &str) -> Result<Vec<u8>, TaggerError> {
+    let file = FsFile::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(hasher.finalize().to_vec())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
+    use tempfile::TempDir;
     type TR = Result<(), TaggerError>;
 
     fn test_app() -> Result<App, TaggerError> {
@@ -155,13 +590,21 @@ mod tests {
         Ok(App { connection })
     }
 
+    // create_file now hashes the file's content, so tests need a real path to read from.
+    fn touch(dir: &TempDir, name: &str) -> String {
+        let path = dir.path().join(name);
+        std::fs::write(&path, name).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
     #[test]
     fn test_get_file_id() -> TR {
+        let dir = TempDir::new().unwrap();
         let mut app = test_app()?;
-        let filename = "abc";
-        assert!(app.get_file(filename).is_none());
-        app.create_file(filename).unwrap();
-        assert!(app.get_file(filename).is_some());
+        let filename = touch(&dir, "abc");
+        assert!(app.get_file(&filename)?.is_none());
+        app.create_file(&filename).unwrap();
+        assert!(app.get_file(&filename)?.is_some());
         Ok(())
     }
 
@@ -169,36 +612,136 @@ mod tests {
     fn test_get_tag_id() -> TR {
         let mut app = test_app()?;
         let tagname = "abc";
-        assert!(app.get_tag(tagname).is_none());
+        assert!(app.get_tag(tagname)?.is_none());
         app.create_tag(tagname).unwrap();
-        assert!(app.get_tag(tagname).is_some());
+        assert!(app.get_tag(tagname)?.is_some());
         Ok(())
     }
 
     #[test]
     fn tag_a_file() -> TR {
+        let dir = TempDir::new().unwrap();
         let mut app = test_app()?;
         let filename = "abc";
-        let tagname = "cba";
-        let file_id = app.create_file(tagname)?;
+        let tagname = touch(&dir, "cba");
+        let file_id = app.create_file(&tagname)?;
         let tag_id = app.create_tag(filename)?;
-        app.tag_file(tag_id, file_id);
+        app.tag_file(tag_id, file_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn create_file_dedupes_identical_content_on_move() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let original = dir.path().join("a");
+        std::fs::write(&original, "same content").unwrap();
+        let id_a = app.create_file(original.to_str().unwrap())?;
+
+        // The old path is actually gone, so this is a move: reuse the row.
+        std::fs::remove_file(&original).unwrap();
+        let moved = dir.path().join("a-renamed");
+        std::fs::write(&moved, "same content").unwrap();
+        let id_b = app.create_file(moved.to_str().unwrap())?;
+
+        assert_eq!(id_a, id_b);
+        assert!(app.get_file(original.to_str().unwrap())?.is_none());
+        assert!(app.get_file(moved.to_str().unwrap())?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn create_file_keeps_coexisting_duplicate_content_as_separate_rows() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let original = dir.path().join("a");
+        std::fs::write(&original, "same content").unwrap();
+        // Both paths are present on disk at once, so this is a duplicate, not a move: the
+        // second `create_file` must not steal the first row out from under `original`.
+        let duplicate = dir.path().join("a-copy");
+        std::fs::write(&duplicate, "same content").unwrap();
+
+        let id_a = app.create_file(original.to_str().unwrap())?;
+        let id_b = app.create_file(duplicate.to_str().unwrap())?;
+
+        assert_ne!(id_a, id_b);
+        assert!(app.get_file(original.to_str().unwrap())?.is_some());
+        assert!(app.get_file(duplicate.to_str().unwrap())?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn create_file_refreshes_metadata_on_reuse() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let original = dir.path().join("a.txt");
+        std::fs::write(&original, "same content").unwrap();
+        let id_a = app.create_file(original.to_str().unwrap())?;
+
+        // Same content, but a different extension, and the old path is gone: a real move, so
+        // the reused row should pick up the new MIME type rather than keep the stale one.
+        std::fs::remove_file(&original).unwrap();
+        let moved = dir.path().join("a.png");
+        std::fs::write(&moved, "same content").unwrap();
+        let id_b = app.create_file(moved.to_str().unwrap())?;
+        assert_eq!(id_a, id_b);
+
+        let files = app.get_files_by_mime("image/png")?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].id, id_a);
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_file_captures_metadata() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let path = touch(&dir, "photo.png");
+
+        app.create_file(&path)?;
+        let files = app.get_files_by_mime("image/png")?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, path);
+        assert_eq!(files[0].size_bytes, "photo.png".len() as i64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_files_bulk_inserts_and_tag_files_bulk_tags() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let a = touch(&dir, "a");
+        let b = touch(&dir, "b");
+
+        let ids = app.create_files([a.as_str(), b.as_str()])?;
+        assert_eq!(ids.len(), 2);
+
+        let tag = app.create_tag("tag")?;
+        app.tag_files(&[(tag, ids[0]), (tag, ids[1])])?;
+
+        let tagged: Vec<FileID> = app.get_files_for_tag(tag)?.into_iter().map(|f| f.id).collect();
+        assert!(tagged.contains(&ids[0]));
+        assert!(tagged.contains(&ids[1]));
+
         Ok(())
     }
 
     #[test]
     fn get_files_for_tag() -> TR {
+        let dir = TempDir::new().unwrap();
         let mut app = test_app()?;
-        let a = app.create_file("a")?;
+        let a = app.create_file(&touch(&dir, "a"))?;
         println!("A ID: {:?}", a);
-        let b = app.create_file("b")?;
+        let b = app.create_file(&touch(&dir, "b"))?;
         println!("B ID: {:?}", b);
-        let c = app.create_file("c")?;
+        let c = app.create_file(&touch(&dir, "c"))?;
         println!("C ID: {:?}", c);
         let tag = app.create_tag("tag")?;
 
-        app.tag_file(tag, a);
-        app.tag_file(tag, b);
+        app.tag_file(tag, a)?;
+        app.tag_file(tag, b)?;
 
         let res = app.get_files_for_tag(tag)?;
         println!("Res: {:?}", res);
@@ -211,6 +754,239 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn search_combines_tags_with_all_any_and_not() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let cat = app.create_file(&touch(&dir, "cat"))?;
+        let dog = app.create_file(&touch(&dir, "dog"))?;
+        let bird = app.create_file(&touch(&dir, "bird"))?;
+
+        let cat_tag = app.create_tag("cat")?;
+        let pet_tag = app.create_tag("pet")?;
+        let archived_tag = app.create_tag("archived")?;
+
+        app.tag_files(&[
+            (cat_tag, cat),
+            (pet_tag, cat),
+            (pet_tag, dog),
+            (pet_tag, bird),
+            (archived_tag, bird),
+        ])?;
+
+        // pet AND cat NOT archived -> just "cat"
+        let query = TagQuery::All(vec![
+            TagQuery::Tag("pet".to_string()),
+            TagQuery::Tag("cat".to_string()),
+            TagQuery::Not(Box::new(TagQuery::Tag("archived".to_string()))),
+        ]);
+        let ids: Vec<FileID> = app.search(&query)?.into_iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![cat]);
+
+        // pet AND (cat OR archived) -> "cat" and "bird"
+        let query = TagQuery::All(vec![
+            TagQuery::Tag("pet".to_string()),
+            TagQuery::Any(vec![
+                TagQuery::Tag("cat".to_string()),
+                TagQuery::Tag("archived".to_string()),
+            ]),
+        ]);
+        let mut ids: Vec<FileID> = app.search(&query)?.into_iter().map(|f| f.id).collect();
+        ids.sort_by_key(|id| id.0);
+        let mut expected = vec![cat, bird];
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(ids, expected);
+
+        Ok(())
+    }
+
+    // Distinguishes `(pet AND cat) OR archived` from `pet AND (cat OR archived)` with a file
+    // that's archived but not a pet: only the second grouping excludes it.
+    #[test]
+    fn search_groups_any_within_all_rather_than_flattening() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let cat = app.create_file(&touch(&dir, "cat"))?;
+        let stray = app.create_file(&touch(&dir, "stray"))?;
+
+        let cat_tag = app.create_tag("cat")?;
+        let pet_tag = app.create_tag("pet")?;
+        let archived_tag = app.create_tag("archived")?;
+
+        app.tag_files(&[
+            (cat_tag, cat),
+            (pet_tag, cat),
+            (archived_tag, stray),
+        ])?;
+
+        // pet AND (cat OR archived) -> just "cat": `stray` is archived but not a pet, so it
+        // must be excluded by the outer AND rather than smuggled in by a flattened OR.
+        let query = TagQuery::All(vec![
+            TagQuery::Tag("pet".to_string()),
+            TagQuery::Any(vec![
+                TagQuery::Tag("cat".to_string()),
+                TagQuery::Tag("archived".to_string()),
+            ]),
+        ]);
+        let ids: Vec<FileID> = app.search(&query)?.into_iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![cat]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_handles_empty_all_and_any() -> TR {
+        let dir = TempDir::new().unwrap();
+        let mut app = test_app()?;
+        let cat = app.create_file(&touch(&dir, "cat"))?;
+
+        // All([]) is vacuously true: every file matches.
+        let ids: Vec<FileID> = app
+            .search(&TagQuery::All(vec![]))?
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(ids, vec![cat]);
+
+        // Any([]) is vacuously false: nothing matches.
+        let ids: Vec<FileID> = app
+            .search(&TagQuery::Any(vec![]))?
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        assert!(ids.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_adds_seens_and_orphans_files() -> TR {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a"), "a").unwrap();
+        std::fs::write(dir.path().join("b"), "b").unwrap();
+        let mut app = test_app()?;
+
+        let report = app.scan(dir.path(), ScanOptions::default())?;
+        assert_eq!(report.added, 2);
+        assert_eq!(report.seen, 2);
+        assert_eq!(report.orphaned, 0);
+
+        std::fs::remove_file(dir.path().join("a")).unwrap();
+
+        let report = app.scan(dir.path(), ScanOptions::default())?;
+        assert_eq!(report.added, 0);
+        assert_eq!(report.seen, 1);
+        assert_eq!(report.orphaned, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_keeps_coexisting_duplicate_content_as_separate_rows() -> TR {
+        let dir = TempDir::new().unwrap();
+        // Two distinct, simultaneously-present files with identical bytes (e.g. two empty
+        // files) must not collapse into one row: neither path is "gone", so neither is a move.
+        std::fs::write(dir.path().join("a"), "").unwrap();
+        std::fs::write(dir.path().join("b"), "").unwrap();
+        let mut app = test_app()?;
+
+        let report = app.scan(dir.path(), ScanOptions::default())?;
+        assert_eq!(report.added, 2);
+        assert_eq!(report.seen, 2);
+
+        let a = app.get_file(dir.path().join("a").to_str().unwrap())?;
+        let b = app.get_file(dir.path().join("b").to_str().unwrap())?;
+        assert!(a.is_some());
+        assert!(b.is_some());
+        assert_ne!(a, b);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_renames_one_of_several_coexisting_duplicates_without_losing_tags() -> TR {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a"), "").unwrap();
+        std::fs::write(dir.path().join("b"), "").unwrap();
+        let mut app = test_app()?;
+        app.scan(dir.path(), ScanOptions::default())?;
+
+        let a = app
+            .get_file(dir.path().join("a").to_str().unwrap())?
+            .unwrap();
+        let tag = app.create_tag("keep-me")?;
+        app.tag_file(tag, a)?;
+
+        // Rename `a`, while `b` (same content, same hash) is still present: this must reuse
+        // `a`'s row specifically, not whichever same-hash row the lookup happens to return.
+        std::fs::rename(dir.path().join("a"), dir.path().join("a2")).unwrap();
+        app.scan(dir.path(), ScanOptions::default())?;
+
+        let a2 = app
+            .get_file(dir.path().join("a2").to_str().unwrap())?
+            .unwrap();
+        assert_eq!(a2, a, "renaming a must reuse a's row, not b's");
+
+        let b = app
+            .get_file(dir.path().join("b").to_str().unwrap())?
+            .unwrap();
+        assert_ne!(b, a);
+
+        let tagged: Vec<FileID> = app.get_files_for_tag(tag)?.into_iter().map(|f| f.id).collect();
+        assert_eq!(tagged, vec![a2], "a's tag must follow its row through the rename");
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_does_not_orphan_files_under_a_sibling_directory() -> TR {
+        let parent = TempDir::new().unwrap();
+        let root = parent.path().join("docs");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("a"), "a").unwrap();
+
+        // Shares `root`'s path as a string prefix, but isn't a descendant of it.
+        let sibling = parent.path().join("docs-backup");
+        std::fs::create_dir(&sibling).unwrap();
+        std::fs::write(sibling.join("b"), "b").unwrap();
+
+        let mut app = test_app()?;
+        app.scan(&root, ScanOptions::default())?;
+        app.scan(&sibling, ScanOptions::default())?;
+
+        let report = app.scan(&root, ScanOptions::default())?;
+        assert_eq!(report.orphaned, 0, "sibling directory's file must not be orphaned");
+        assert!(app.get_file(sibling.join("b").to_str().unwrap())?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_skips_cachedir_tagged_and_ignored_directories() -> TR {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("tracked"), "tracked").unwrap();
+
+        let cache_dir = dir.path().join("cache");
+        std::fs::create_dir(&cache_dir).unwrap();
+        std::fs::write(
+            cache_dir.join("CACHEDIR.TAG"),
+            "Signature: 8a477f597d28d172789f06886806bc55 extra trailing bytes are ignored",
+        )
+        .unwrap();
+        std::fs::write(cache_dir.join("ignored"), "ignored").unwrap();
+
+        let git_dir = dir.path().join(".git");
+        std::fs::create_dir(&git_dir).unwrap();
+        std::fs::write(git_dir.join("ignored"), "ignored").unwrap();
+
+        let mut app = test_app()?;
+        let report = app.scan(dir.path(), ScanOptions::default())?;
+        assert_eq!(report.added, 1);
+        assert_eq!(report.seen, 1);
+
+        Ok(())
+    }
+
     #[test]
     fn migrations_test() {
         assert!(MIGRATIONS.validate().is_ok());